@@ -16,6 +16,10 @@ pub struct Args {
     #[doc = "only consider builds for the given target triple"]
     pub(crate) target: Option<String>,
 
+    #[argh(option, short = 'p', arg_name = "NAME")]
+    #[doc = "use binaries from the named package"]
+    pub(crate) package: Option<String>,
+
     #[argh(option, arg_name = "NAME")]
     #[doc = "attach to the named binary"]
     pub(crate) bin: Option<String>,
@@ -24,6 +28,10 @@ pub struct Args {
     #[doc = "attach to the named example"]
     pub(crate) example: Option<String>,
 
+    #[argh(switch)]
+    #[doc = "list matching executables instead of attaching to one"]
+    pub(crate) list: bool,
+
     #[argh(positional, greedy)]
     #[doc = "arguments to pass to probe-rs"]
     pub(crate) probe_args: Vec<String>,
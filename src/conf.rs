@@ -1,28 +1,116 @@
-use std::{fs::read_to_string, io::ErrorKind, str::FromStr};
+use std::{
+    collections::HashSet,
+    env,
+    fs::read_to_string,
+    io::ErrorKind,
+    path::{Path, PathBuf},
+    process::Command,
+    str::FromStr,
+};
 
 use cargo_metadata::Package;
-use cargo_platform::Platform;
-use toml::Table;
+use cargo_platform::{Cfg, Platform};
+use toml::{Table, Value};
 
 use crate::Result;
 
-/// Load `.cargo/config.toml`, if it exists.
+/// Load and merge every `.cargo/config.toml` between the package directory and the filesystem
+/// root, plus the user's `$CARGO_HOME/config.toml`, the way cargo itself resolves config.
+/// Nearer files take precedence over farther ones.
 pub(crate) fn load_config(package: &Package) -> Result<Option<Table>> {
-    let path = package.manifest_path.with_file_name(".cargo/config.toml");
+    let mut configs = vec![];
+    // The default `$CARGO_HOME` (`~/.cargo`) sits under virtually every project directory, so
+    // the ancestor walk and the explicit home lookup below can land on the same directory; track
+    // what's already been read so it isn't merged in twice.
+    let mut visited = HashSet::new();
 
-    let file = match read_to_string(&path) {
+    for dir in package.manifest_path.parent().unwrap().ancestors() {
+        let cargo_dir = dir.as_std_path().join(".cargo");
+
+        if visited.insert(canonicalize_or(&cargo_dir)) {
+            if let Some(table) = read_config_dir(&cargo_dir)? {
+                configs.push(table);
+            }
+        }
+    }
+
+    if let Some(home) = cargo_home() {
+        if visited.insert(canonicalize_or(&home)) {
+            if let Some(table) = read_config_dir(&home)? {
+                configs.push(table);
+            }
+        }
+    }
+
+    // `configs` is ordered nearest-first; fold from farthest to nearest so that nearer values
+    // take precedence.
+    Ok(configs.into_iter().rev().reduce(merge))
+}
+
+/// Canonicalize a directory for de-duplication purposes, falling back to the path as given if it
+/// doesn't exist (in which case it can't hold a config file worth double-counting anyway).
+fn canonicalize_or(path: &Path) -> PathBuf {
+    path.canonicalize().unwrap_or_else(|_| path.to_owned())
+}
+
+/// Read `config.toml`, falling back to the legacy extensionless `config`, from a directory that
+/// is either the `.cargo` directory of the package directory or one of its ancestors, or
+/// `$CARGO_HOME`.
+fn read_config_dir(dir: &Path) -> Result<Option<Table>> {
+    if let Some(table) = read_config_file(&dir.join("config.toml"))? {
+        return Ok(Some(table));
+    }
+
+    read_config_file(&dir.join("config"))
+}
+
+fn read_config_file(path: &Path) -> Result<Option<Table>> {
+    let file = match read_to_string(path) {
         Ok(file) => file,
         Err(err) if err.kind() == ErrorKind::NotFound => return Ok(None),
-        Err(_) => return Err(format!("could not read {path}").into()),
+        Err(_) => return Err(format!("could not read {}", path.display()).into()),
     };
 
     let Ok(table) = file.parse() else {
-        return Err(format!("could not parse {path}").into());
+        return Err(format!("could not parse {}", path.display()).into());
     };
 
     Ok(Some(table))
 }
 
+/// The directory cargo stores its global configuration in.
+fn cargo_home() -> Option<PathBuf> {
+    if let Some(dir) = env::var_os("CARGO_HOME") {
+        return Some(PathBuf::from(dir));
+    }
+
+    let home = env::var_os("HOME").or_else(|| env::var_os("USERPROFILE"))?;
+
+    Some(PathBuf::from(home).join(".cargo"))
+}
+
+/// Deep-merge `overlay` into `base`, with `overlay` taking precedence: nested tables are merged
+/// recursively, arrays are concatenated with `overlay`'s values appearing first, and other
+/// conflicting values are replaced by `overlay`'s.
+fn merge(base: Table, overlay: Table) -> Table {
+    let mut merged = base;
+
+    for (key, value) in overlay {
+        let combined = match (merged.remove(&key), value) {
+            (Some(Value::Table(base)), Value::Table(overlay)) => Value::Table(merge(base, overlay)),
+            (Some(Value::Array(base)), Value::Array(mut overlay)) => {
+                overlay.extend(base);
+                Value::Array(overlay)
+            }
+            (_, value) => value,
+        };
+
+        merged.insert(key, combined);
+    }
+
+    merged
+}
+
 /// Find build target in configuration.
 pub(crate) fn find_build_target(config: &Table) -> Option<String> {
     let build = config.get("build").and_then(|v| v.as_table());
@@ -37,34 +125,171 @@ pub(crate) fn find_probe_args(config: &Table, target: Option<&String>) -> Result
         return Ok(vec![]);
     };
 
+    // Only the presence of a `cfg()` selector requires knowing the active cfgs; plain named
+    // selectors never read them, so don't pay for shelling out to rustc unless one is present.
+    let needs_cfgs = targets
+        .keys()
+        .any(|selector| matches!(Platform::from_str(selector), Ok(Platform::Cfg(_))));
+
+    let cfgs = if needs_cfgs {
+        target_cfgs(target.map(String::as_str))?
+    } else {
+        vec![]
+    };
+
     let runners = targets
         .iter()
         .filter(|(selector, _)| {
-            let Some(target) = &target else {
-                return true;
-            };
-
             let Ok(platform) = Platform::from_str(selector) else {
                 return true;
             };
 
-            platform.matches(target, &[])
+            match (&platform, target) {
+                (Platform::Cfg(_), _) => platform.matches("", &cfgs),
+                (Platform::Name(_), None) => true,
+                (Platform::Name(_), Some(target)) => platform.matches(target, &cfgs),
+            }
         })
-        .filter_map(|(_, v)| v.get("runner").and_then(|v| v.as_str()))
-        .filter_map(|r| r.strip_prefix("probe-rs run "))
+        .filter_map(|(_, v)| v.get("runner"))
+        .map(probe_args_from_runner)
         .collect::<Vec<_>>();
 
-    if runners.len() > 1 {
+    let valid = runners.iter().filter(|r| r.is_ok()).count();
+
+    if valid > 1 {
         return Err("found more than one runner configuration".into());
     }
 
-    let Ok([runner]) = TryInto::<[_; 1]>::try_into(runners) else {
-        return Ok(vec![]);
+    if valid == 1 {
+        return runners.into_iter().find(Result::is_ok).unwrap();
+    }
+
+    // No matching selector configured a usable probe-rs runner. If any runner was found at all,
+    // surface why it couldn't be used instead of silently doing nothing, no matter how many such
+    // unusable runners there were.
+    if let Some(err) = runners.into_iter().find_map(Result::err) {
+        return Err(err);
+    }
+
+    Ok(vec![])
+}
+
+/// Extract `probe-rs` arguments from a `runner` value, which cargo allows to be either a single
+/// string or an array of program and argument strings.
+fn probe_args_from_runner(runner: &Value) -> Result<Vec<String>> {
+    let tokens = match runner {
+        Value::String(runner) => {
+            let Some(tokens) = shlex::split(runner) else {
+                return Err("could not parse runner command".into());
+            };
+
+            tokens
+        }
+        Value::Array(items) => items
+            .iter()
+            .map(|item| {
+                item.as_str()
+                    .map(str::to_owned)
+                    .ok_or_else(|| "runner array must contain only strings".into())
+            })
+            .collect::<Result<Vec<_>>>()?,
+        _ => return Err("runner must be a string or an array of strings".into()),
+    };
+
+    probe_args_from_tokens(&tokens)
+}
+
+/// Recognize a `probe-rs run` invocation, in any binary/subcommand/args tokenization, and return
+/// the arguments that follow unchanged, for forwarding to `probe-rs attach`. Wrapper runners that
+/// don't resolve to `probe-rs run` are rejected, since their arguments can't be forwarded.
+fn probe_args_from_tokens(tokens: &[String]) -> Result<Vec<String>> {
+    let [binary, subcommand, args @ ..] = tokens else {
+        return Err(not_a_probe_rs_run_err(tokens));
+    };
+
+    let is_probe_rs = Path::new(binary)
+        .file_stem()
+        .is_some_and(|stem| stem == "probe-rs");
+
+    if !is_probe_rs || subcommand != "run" {
+        return Err(not_a_probe_rs_run_err(tokens));
+    }
+
+    Ok(args.to_vec())
+}
+
+fn not_a_probe_rs_run_err(tokens: &[String]) -> Box<dyn std::error::Error> {
+    format!(
+        "runner `{}` is not a `probe-rs run` invocation",
+        tokens.join(" ")
+    )
+    .into()
+}
+
+/// Resolve `CARGO_TARGET_<TRIPLE>_RUNNER`, if a target triple is known and the variable is set.
+pub(crate) fn find_env_runner(target: Option<&String>) -> Result<Option<Vec<String>>> {
+    let Some(target) = target else {
+        return Ok(None);
+    };
+
+    let var = format!("CARGO_TARGET_{}_RUNNER", screaming_snake_case(target));
+
+    let Some(runner) = env::var_os(&var) else {
+        return Ok(None);
     };
 
-    let Some(probe_args) = shlex::split(runner) else {
-        return Err("could not parse probe-rs arguments".into());
+    let Some(runner) = runner.to_str() else {
+        return Err(format!("{var} is not valid UTF-8").into());
+    };
+
+    let Some(tokens) = shlex::split(runner) else {
+        return Err(format!("could not parse {var}").into());
+    };
+
+    Ok(Some(probe_args_from_tokens(&tokens)?))
+}
+
+/// Upper-case a target triple and replace non-alphanumeric characters with underscores, as cargo
+/// does to derive a target's environment variable names.
+fn screaming_snake_case(target: &str) -> String {
+    target
+        .chars()
+        .map(|c| {
+            if c.is_ascii_alphanumeric() {
+                c.to_ascii_uppercase()
+            } else {
+                '_'
+            }
+        })
+        .collect()
+}
+
+/// Query the cfgs active for a target, the way cargo itself resolves `cfg()` selectors.
+fn target_cfgs(target: Option<&str>) -> Result<Vec<Cfg>> {
+    let rustc = env::var("RUSTC").unwrap_or_else(|_| "rustc".to_owned());
+
+    let mut command = Command::new(rustc);
+    command.args(["--print", "cfg"]);
+
+    if let Some(target) = target {
+        command.args(["--target", target]);
+    }
+
+    let output = command
+        .output()
+        .map_err(|err| format!("could not run rustc: {err}"))?;
+
+    if !output.status.success() {
+        return Err("rustc --print cfg did not succeed".into());
+    }
+
+    let Ok(stdout) = String::from_utf8(output.stdout) else {
+        return Err("rustc --print cfg produced non-UTF-8 output".into());
     };
 
-    Ok(probe_args)
+    stdout
+        .lines()
+        .filter(|line| !line.is_empty())
+        .map(|line| Cfg::from_str(line).map_err(|_| format!("could not parse cfg {line}").into()))
+        .collect()
 }
@@ -1,4 +1,10 @@
-use std::{convert::Infallible, os::unix::process::CommandExt, path::PathBuf, process::Command};
+use std::{
+    env,
+    os::unix::process::CommandExt,
+    path::{Path, PathBuf},
+    process::Command,
+    time::SystemTime,
+};
 
 use cargo_metadata::{MetadataCommand, camino::Utf8Path};
 use walkdir::WalkDir;
@@ -10,7 +16,7 @@ mod conf;
 
 type Result<T> = std::result::Result<T, Box<dyn std::error::Error>>;
 
-pub fn attach(args: Args) -> Result<Infallible> {
+pub fn attach(args: Args) -> Result<()> {
     if args.release && args.debug {
         return Err("the release and debug flags may not be used together".into());
     }
@@ -23,11 +29,34 @@ pub fn attach(args: Args) -> Result<Infallible> {
         // Trim trailing newlines from Cargo's errors.
         .map_err(|e| e.to_string().trim().to_owned())?;
 
-    let Some(package) = metadata.root_package() else {
-        return Err("could not determine which package to use binaries from".into());
+    let package = match &args.package {
+        Some(name) => metadata
+            .packages
+            .iter()
+            .find(|package| &package.name == name)
+            .ok_or_else(|| format!("no package named {name} found in the workspace"))?,
+        None => metadata
+            .root_package()
+            .ok_or("could not determine which package to use binaries from")?,
     };
 
-    let config = if args.probe_args.is_empty() || args.target.is_none() {
+    // `build_target` resolved only as far as the CLI and environment go; used to decide whether
+    // the config is worth consulting at all, and as the final value if it turns out to be.
+    let cli_target = args.target.or(env::var("CARGO_BUILD_TARGET").ok());
+
+    let env_runner = if !args.list && args.probe_args.is_empty() {
+        conf::find_env_runner(cli_target.as_ref())?
+    } else {
+        None
+    };
+
+    // A CLI flag or environment variable can make consulting the config unnecessary; only pay
+    // for discovering and parsing it when something still needs to come from there. `--list`
+    // never execs `probe-rs`, so it never needs a runner out of the config, and neither does a
+    // runner already resolved via `CARGO_TARGET_<TRIPLE>_RUNNER`.
+    let needs_config_for_target = cli_target.is_none();
+    let needs_config_for_runner = !args.list && args.probe_args.is_empty() && env_runner.is_none();
+    let config = if needs_config_for_target || needs_config_for_runner {
         conf::load_config(package)?
     } else {
         None
@@ -50,31 +79,35 @@ pub fn attach(args: Args) -> Result<Infallible> {
         None
     };
 
-    let build_target = if args.target.is_none() {
-        if let Some(config) = &config {
-            conf::find_build_target(config)
-        } else {
-            None
-        }
-    } else {
-        args.target
-    };
+    // CLI flag > environment variable > `.cargo/config.toml`.
+    let build_target = cli_target.or_else(|| config.as_ref().and_then(conf::find_build_target));
 
-    let probe_args = if args.probe_args.is_empty() {
-        if let Some(config) = &config {
-            conf::find_probe_args(config, build_target.as_ref())?
-        } else {
-            vec![]
-        }
-    } else {
+    if args.list {
+        list_candidates(
+            &metadata.target_directory,
+            &binary_names,
+            build_mode.as_deref(),
+            build_target.as_deref(),
+        );
+
+        return Ok(());
+    }
+
+    let probe_args = if !args.probe_args.is_empty() {
         args.probe_args
+    } else if let Some(probe_args) = env_runner {
+        probe_args
+    } else if let Some(config) = &config {
+        conf::find_probe_args(config, build_target.as_ref())?
+    } else {
+        vec![]
     };
 
     let Some(executable) = find_executable(
         &metadata.target_directory,
-        binary_names,
-        build_mode,
-        build_target,
+        &binary_names,
+        build_mode.as_deref(),
+        build_target.as_deref(),
     ) else {
         return Err(format!("no matching executable found for package {}", package.name).into());
     };
@@ -88,35 +121,110 @@ pub fn attach(args: Args) -> Result<Infallible> {
     Err(error.into())
 }
 
+/// A binary or example produced by a build, with the details needed to tell candidates apart.
+struct Candidate {
+    name: String,
+    build_mode: Option<String>,
+    target_triple: Option<String>,
+    modified: SystemTime,
+    path: PathBuf,
+}
+
 fn find_executable(
     base: &Utf8Path,
-    binary_names: Vec<String>,
-    build_mode: Option<String>,
-    build_target: Option<String>,
+    binary_names: &[String],
+    build_mode: Option<&str>,
+    build_target: Option<&str>,
 ) -> Option<PathBuf> {
+    candidates(base, binary_names, build_mode, build_target)
+        .into_iter()
+        .max_by_key(|candidate| candidate.modified)
+        .map(|candidate| candidate.path)
+}
+
+/// Print every candidate executable, newest-first, instead of execing `probe-rs`.
+fn list_candidates(
+    base: &Utf8Path,
+    binary_names: &[String],
+    build_mode: Option<&str>,
+    build_target: Option<&str>,
+) {
+    let mut candidates = candidates(base, binary_names, build_mode, build_target);
+    candidates.sort_by_key(|candidate| std::cmp::Reverse(candidate.modified));
+
+    for candidate in candidates {
+        let mode = candidate.build_mode.as_deref().unwrap_or("host");
+        let triple = candidate.target_triple.as_deref().unwrap_or("host");
+
+        println!(
+            "{} ({mode}, {triple}, modified {}) {}",
+            candidate.name,
+            modified_ago(candidate.modified),
+            candidate.path.display(),
+        );
+    }
+}
+
+fn candidates(
+    base: &Utf8Path,
+    binary_names: &[String],
+    build_mode: Option<&str>,
+    build_target: Option<&str>,
+) -> Vec<Candidate> {
     let target_files = WalkDir::new(base)
         .max_depth(4)
         .into_iter()
         .filter_map(|e| e.ok())
         .filter(|e| e.file_type().is_file());
 
-    let executables = target_files
+    target_files
         .filter(|e| binary_names.iter().any(|x| x.as_str() == e.file_name()))
-        .filter(|e| {
-            let path = e.path().strip_prefix(base).unwrap().parent().unwrap();
+        .filter_map(|e| {
+            let relative = e.path().strip_prefix(base).unwrap().parent().unwrap();
+            let (target_triple, entry_build_mode) = classify(relative);
+
+            let matches_build_mode =
+                build_mode.is_none_or(|m| entry_build_mode.as_deref() == Some(m));
+            let matches_target_triple =
+                build_target.is_none_or(|t| target_triple.as_deref() == Some(t));
+
+            (matches_build_mode && matches_target_triple).then(|| Candidate {
+                name: e.file_name().to_string_lossy().into_owned(),
+                build_mode: entry_build_mode,
+                target_triple,
+                modified: e.metadata().unwrap().modified().unwrap(),
+                path: e.into_path(),
+            })
+        })
+        .collect()
+}
 
-            let matches_build_mode = build_mode
-                .as_deref()
-                .is_none_or(|m| path.iter().any(|c| c == m));
+/// Read a target triple and build mode out of a path relative to the target directory, following
+/// cargo's `<target>/[<triple>/]<profile>/...` layout. Only `debug` and `release` are recognized
+/// as profiles; any other first component is read as a target triple with no build mode.
+fn classify(path: &Path) -> (Option<String>, Option<String>) {
+    let components = path.iter().filter_map(|c| c.to_str()).collect::<Vec<_>>();
 
-            let matches_target_triple = build_target
-                .as_deref()
-                .is_none_or(|t| path.iter().any(|c| c == t));
+    match *components {
+        [mode @ ("debug" | "release"), ..] => (None, Some(mode.to_owned())),
+        [triple, mode @ ("debug" | "release"), ..] => {
+            (Some(triple.to_owned()), Some(mode.to_owned()))
+        }
+        [triple, ..] => (Some(triple.to_owned()), None),
+        [] => (None, None),
+    }
+}
 
-            matches_build_mode && matches_target_triple
-        });
+/// Render how long ago a file was modified, without pulling in a date/time dependency.
+fn modified_ago(modified: SystemTime) -> String {
+    let Ok(elapsed) = SystemTime::now().duration_since(modified) else {
+        return "just now".to_owned();
+    };
 
-    executables
-        .max_by_key(|e| e.metadata().unwrap().modified().unwrap())
-        .map(|e| e.into_path())
+    match elapsed.as_secs() {
+        0..=59 => "just now".to_owned(),
+        secs @ 60..=3599 => format!("{}m ago", secs / 60),
+        secs @ 3600..=86_399 => format!("{}h ago", secs / 3600),
+        secs => format!("{}d ago", secs / 86_400),
+    }
 }
@@ -3,10 +3,10 @@ use std::process::ExitCode;
 fn main() -> ExitCode {
     let args = argh::cargo_from_env();
 
-    // When successful, this does not return.
-    let Err(error) = cargo_attach::attach(args);
+    if let Err(error) = cargo_attach::attach(args) {
+        eprintln!("error: {error}");
+        return ExitCode::FAILURE;
+    }
 
-    eprintln!("error: {error}");
-
-    ExitCode::FAILURE
+    ExitCode::SUCCESS
 }